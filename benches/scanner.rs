@@ -0,0 +1,163 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use rlox::Scanner;
+
+// Counts heap allocations so interning and other allocation work shows up
+// next to throughput.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+const MEASURE_TIME: Duration = Duration::from_secs(2);
+
+// xorshift64, so every run scans byte-for-byte identical sources.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn ident(&mut self) -> String {
+        let len = 1 + self.below(8);
+        (0..len)
+            .map(|_| (b'a' + self.below(26) as u8) as char)
+            .collect()
+    }
+
+    fn number(&mut self) -> String {
+        if self.below(2) == 0 {
+            self.below(100_000).to_string()
+        } else {
+            format!("{}.{}", self.below(10_000), self.below(1_000))
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let len = self.below(40);
+        let body: String = (0..len)
+            .map(|_| (b' ' + self.below(94) as u8) as char)
+            .filter(|c| *c != '"')
+            .collect();
+        format!("\"{}\"", body)
+    }
+}
+
+fn mixed(rng: &mut Rng, target: usize) -> String {
+    let mut source = String::new();
+    while source.len() < target {
+        let line = match rng.below(6) {
+            0 => format!("var {} = {};\n", rng.ident(), rng.number()),
+            1 => format!("print {};\n", rng.string()),
+            2 => format!(
+                "if ({} <= {}) {{ {} = {} + 1; }}\n",
+                rng.ident(),
+                rng.number(),
+                rng.ident(),
+                rng.ident()
+            ),
+            3 => format!(
+                "while (!{} and {} != nil) {{ {}.{}(); }}\n",
+                rng.ident(),
+                rng.ident(),
+                rng.ident(),
+                rng.ident()
+            ),
+            4 => format!("// {}\n", rng.ident()),
+            _ => format!(
+                "fun {}({}, {}) {{ return {} * {} / {}; }}\n",
+                rng.ident(),
+                rng.ident(),
+                rng.ident(),
+                rng.ident(),
+                rng.number(),
+                rng.ident()
+            ),
+        };
+        source.push_str(&line);
+    }
+    source
+}
+
+fn strings(rng: &mut Rng, target: usize) -> String {
+    let mut source = String::new();
+    while source.len() < target {
+        source.push_str(&format!("print {} + {};\n", rng.string(), rng.string()));
+    }
+    source
+}
+
+fn numbers(rng: &mut Rng, target: usize) -> String {
+    let mut source = String::new();
+    while source.len() < target {
+        source.push_str(&format!(
+            "var {} = {} - {} * {};\n",
+            rng.ident(),
+            rng.number(),
+            rng.number(),
+            rng.number()
+        ));
+    }
+    source
+}
+
+fn bench(name: &str, source: String) {
+    let mut tokens = 0;
+    let mut iterations = 0u32;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+
+    while iterations == 0 || started.elapsed() < MEASURE_TIME {
+        let mut scanner = Scanner::new(source.clone());
+        tokens += scanner.scan_tokens().len();
+        iterations += 1;
+    }
+
+    let elapsed = started.elapsed().as_secs_f64();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    println!(
+        "{:<10} {:>8} bytes {:>6} iters {:>12.3} ms/iter {:>14.0} tokens/s {:>10} allocs/iter",
+        name,
+        source.len(),
+        iterations,
+        elapsed * 1000.0 / iterations as f64,
+        tokens as f64 / elapsed,
+        allocations / iterations as usize
+    );
+}
+
+fn main() {
+    let mut rng = Rng(SEED);
+
+    bench("small", mixed(&mut rng, 1 << 10));
+    bench("medium", mixed(&mut rng, 1 << 13));
+    bench("large", mixed(&mut rng, 1 << 15));
+    bench("strings", strings(&mut rng, 1 << 13));
+    bench("numbers", numbers(&mut rng, 1 << 13));
+}
@@ -0,0 +1,172 @@
+use crate::token::{Literal, Token, TokenType};
+
+/// Renders tokens as an aligned `LINE  TYPE  LEXEME  LITERAL` table.
+/// Lexemes longer than `width` characters are cut short with an ellipsis.
+pub fn pretty(tokens: &[Token], width: usize) -> String {
+    let rows: Vec<[String; 4]> = tokens
+        .iter()
+        .map(|token| {
+            let literal = match &token.literal {
+                Literal::None => String::new(),
+                literal => format!("{:?}", literal),
+            };
+            // Escape newlines in multi-line strings so rows stay on one line.
+            let lexeme: String = token
+                .lexeme
+                .chars()
+                .map(|c| {
+                    if c.is_control() {
+                        c.escape_default().to_string()
+                    } else {
+                        c.to_string()
+                    }
+                })
+                .collect();
+            [
+                token.line.to_string(),
+                format!("{:?}", token.token_type),
+                truncate(&lexeme, width),
+                literal,
+            ]
+        })
+        .collect();
+
+    let header = ["LINE", "TYPE", "LEXEME", "LITERAL"].map(String::from);
+    let mut widths = header.clone().map(|h| h.chars().count());
+    for row in rows.iter() {
+        for (w, cell) in widths.iter_mut().zip(row.iter()) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let line = format!(
+            "{:>w0$}  {:<w1$}  {:<w2$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+        );
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+
+    // The ellipsis takes up one of the `width` characters.
+    match width.checked_sub(1) {
+        Some(keep) => text.chars().take(keep).chain(Some('…')).collect(),
+        None => String::new(),
+    }
+}
+
+/// Prints each source line followed by a caret row per token that starts on
+/// it, with the carets under the token's lexeme.
+pub fn annotate(source: &str, tokens: &[Token]) -> String {
+    let gutter = source.lines().count().max(1).to_string().len();
+    let mut out = String::new();
+
+    for (i, text) in source.lines().enumerate() {
+        // Tabs would throw the carets off, and a token's column counts a tab
+        // as a single character.
+        let text = text.replace('\t', " ");
+        out.push_str(format!("{:>gutter$} | {}", i + 1, text).trim_end());
+        out.push('\n');
+
+        for token in tokens.iter().filter(|t| start_line(t) == i + 1) {
+            if token.token_type == TokenType::Eof {
+                continue;
+            }
+            let width = token.lexeme.lines().next().map_or(0, |l| l.chars().count());
+            out.push_str(&format!(
+                "{:gutter$} | {}{} {:?}\n",
+                "",
+                " ".repeat(token.column - 1),
+                "^".repeat(width.max(1)),
+                token.token_type,
+            ));
+        }
+    }
+    out
+}
+
+// A token's `line` is the line its last character is on, so a trailing
+// newline (the whole lexeme of a `Newline` token) doesn't move the start.
+fn start_line(token: &Token) -> usize {
+    let lexeme = token.lexeme.strip_suffix('\n').unwrap_or(&token.lexeme);
+    token.line - lexeme.matches('\n').count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scan_all, Scanner};
+
+    #[test]
+    fn pretty_prints_header_and_rows() {
+        let (tokens, _) = scan_all("x = \"a\nb\";");
+        assert_eq!(
+            pretty(&tokens, 20),
+            "\
+LINE  TYPE        LEXEME  LITERAL
+   1  Identifier  x
+   1  Equal       =
+   2  String      \"a\\nb\"  String(\"a\\nb\")
+   2  Semicolon   ;
+   2  Eof
+"
+        );
+    }
+
+    #[test]
+    fn truncate_fits_width() {
+        assert_eq!(truncate("abcdef", 4), "abc…");
+        assert_eq!(truncate("abcdef", 1), "…");
+        assert_eq!(truncate("abcdef", 0), "");
+        assert_eq!(truncate("abc", 3), "abc");
+    }
+
+    #[test]
+    fn annotate_aligns_carets_under_tokens() {
+        let source = "var ab = 10;";
+        let (tokens, _) = scan_all(source);
+        assert_eq!(
+            annotate(source, &tokens),
+            "\
+1 | var ab = 10;
+  | ^^^ Var
+  |     ^^ Identifier
+  |        ^ Equal
+  |          ^^ Number
+  |            ^ Semicolon
+"
+        );
+    }
+
+    #[test]
+    fn annotate_marks_newline_tokens_on_their_line() {
+        let mut scanner = Scanner::new("a\nb\n".to_string());
+        scanner.set_significant_newlines(true);
+        let tokens = scanner.scan_tokens();
+        assert_eq!(
+            annotate(scanner.source(), &tokens),
+            "\
+1 | a
+  | ^ Identifier
+  |  ^ Newline
+2 | b
+  | ^ Identifier
+  |  ^ Newline
+"
+        );
+    }
+}
@@ -0,0 +1,17 @@
+use std::fmt;
+
+use crate::diagnostic;
+
+/// An error found while processing a source, kept so callers can decide how
+/// to report it.
+#[derive(Debug, Clone)]
+pub struct LoxError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LoxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&diagnostic(self.line, "", &self.message, false))
+    }
+}
@@ -0,0 +1,32 @@
+use std::{collections::HashMap, rc::Rc};
+
+/// Id of an interned string. Two symbols from the same `Interner` are equal
+/// exactly when their text is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+/// Deduplicates identifier and keyword lexemes so repeated names share one
+/// allocation and can be compared by id.
+#[derive(Default)]
+pub struct Interner {
+    symbols: HashMap<Rc<str>, Symbol>,
+    names: Vec<Rc<str>>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, text: &str) -> (Symbol, Rc<str>) {
+        if let Some((name, symbol)) = self.symbols.get_key_value(text) {
+            return (*symbol, name.clone());
+        }
+
+        let symbol = Symbol(self.names.len() as u32);
+        let name: Rc<str> = Rc::from(text);
+        self.symbols.insert(name.clone(), symbol);
+        self.names.push(name.clone());
+        (symbol, name)
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.0 as usize]
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub mod dump;
+pub mod error;
+pub mod intern;
+pub mod scanner;
+pub mod token;
+
+pub use error::LoxError;
+pub use intern::{Interner, Symbol};
+pub use scanner::{scan_all, Scanner};
+pub use token::{Literal, Token, TokenType};
+
+static HAD_ERROR: AtomicBool = AtomicBool::new(false);
+static USE_COLOR: AtomicBool = AtomicBool::new(false);
+
+const RED: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+pub struct Lox;
+
+impl Lox {
+    pub fn error(line: usize, message: &str) {
+        Self::report(line, String::from(""), message);
+    }
+
+    fn report(line: usize, on: String, message: &str) {
+        println!(
+            "{}",
+            diagnostic(line, &on, message, USE_COLOR.load(Ordering::Relaxed))
+        );
+        HAD_ERROR.store(true, Ordering::Relaxed);
+    }
+
+    /// Colorizes the diagnostic prefix with ANSI codes. Off by default.
+    pub fn set_color(enabled: bool) {
+        USE_COLOR.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn had_error() -> bool {
+        HAD_ERROR.load(Ordering::Relaxed)
+    }
+}
+
+pub(crate) fn diagnostic(line: usize, on: &str, message: &str, color: bool) -> String {
+    let prefix = format!("[line: {}] Error {}:", line, on);
+    if color {
+        format!("{}{}{} {}", RED, prefix, RESET, message)
+    } else {
+        format!("{} {}", prefix, message)
+    }
+}
+
+/// How `run` prints the scanned tokens.
+pub enum Dump {
+    Debug,
+    /// Aligned table, truncating lexemes to the given width.
+    Pretty(usize),
+    /// Tokens marked under their position in each source line.
+    Annotate,
+}
+
+/// Settings for `run` that come from the command line.
+pub struct Config {
+    pub dump: Dump,
+    /// Overrides the scanner's default string literal limit, in bytes.
+    pub max_string_length: Option<usize>,
+}
+
+pub fn run(source: String, config: &Config) {
+    let mut scanner = Scanner::new(strip_preamble(&source).to_string());
+    if let Some(max_string_length) = config.max_string_length {
+        scanner.set_max_string_length(max_string_length);
+    }
+
+    let tokens = scanner.scan_tokens();
+    for error in scanner.errors() {
+        Lox::error(error.line, &error.message);
+    }
+
+    match &config.dump {
+        Dump::Debug => {
+            for token in tokens.iter() {
+                println!("{:?}", token);
+            }
+        }
+        Dump::Pretty(width) => print!("{}", dump::pretty(&tokens, *width)),
+        Dump::Annotate => print!("{}", dump::annotate(scanner.source(), &tokens)),
+    }
+}
+
+/// Drops a leading UTF-8 BOM and a `#!` line at the very start of the source.
+/// The shebang's newline is kept so line numbers still match the file.
+fn strip_preamble(source: &str) -> &str {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    if !source.starts_with("#!") {
+        return source;
+    }
+
+    match source.find('\n') {
+        Some(end) => &source[end..],
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostic_without_color_is_plain() {
+        let text = diagnostic(3, "", "Unexpected character.", false);
+        assert_eq!(text, "[line: 3] Error : Unexpected character.");
+        assert!(!text.contains('\x1b'));
+    }
+
+    #[test]
+    fn diagnostic_with_color_wraps_prefix() {
+        let text = diagnostic(3, "", "Unexpected character.", true);
+        assert_eq!(
+            text,
+            "\x1b[1;31m[line: 3] Error :\x1b[0m Unexpected character."
+        );
+    }
+
+    #[test]
+    fn strips_bom_and_shebang() {
+        assert_eq!(strip_preamble("\u{feff}print 1;"), "print 1;");
+        assert_eq!(
+            strip_preamble("#!/usr/bin/env rlox\nprint 1;"),
+            "\nprint 1;"
+        );
+        assert_eq!(strip_preamble("\u{feff}#!rlox\nprint 1;"), "\nprint 1;");
+        assert_eq!(strip_preamble("#!rlox"), "");
+    }
+
+    #[test]
+    fn keeps_hash_bang_after_the_first_line() {
+        assert_eq!(strip_preamble("print 1;\n#!x"), "print 1;\n#!x");
+        assert_eq!(strip_preamble(" #!x"), " #!x");
+    }
+}
@@ -1,349 +1,145 @@
-use std::{collections::HashMap, error::Error, fs};
+use std::{
+    env, fs,
+    io::{self, IsTerminal},
+    process,
+};
+
+use rlox::{run, Config, Dump, Lox};
+
+const DEFAULT_LEXEME_WIDTH: usize = 20;
+
+fn read_file(path: &str, config: &Config) -> Result<(), String> {
+    let bytes = fs::read(path).map_err(|_| format!("Could not open file '{}'.", path))?;
+    let source = String::from_utf8(bytes).map_err(|e| {
+        format!(
+            "File '{}' is not valid UTF-8 (at byte {}).",
+            path,
+            e.utf8_error().valid_up_to()
+        )
+    })?;
+    run(source, config);
 
-struct Lox {
-    had_error: bool,
+    Ok(())
 }
 
-impl Lox {
-    fn error(&mut self, line: usize, message: &str) {
-        self.report(line, String::from(""), message);
-    }
-
-    fn report(&mut self, line: usize, on: String, message: &str) {
-        println!("[line: {}] Error {}: {}", line, on, message);
-        self.had_error = true;
-    }
+struct Options {
+    path: String,
+    color: bool,
+    config: Config,
 }
 
-static mut LOX: Lox = Lox { had_error: false };
-
-struct Scanner<'a> {
-    source: String,
-    tokens: Vec<Token>,
-
-    start: usize,
-    current: usize,
-    line: usize,
-
-    keywords: HashMap<&'a str, TokenType>,
-}
-
-impl Scanner<'_> {
-    pub fn scan_tokens(&mut self) -> Vec<Token> {
-        while !self.is_at_end() {
-            self.start = self.current;
-            self.scan_token()
-        }
-
-        self.tokens.push(Token {
-            token_type: TokenType::Eof,
-            lexeme: "".to_string(),
-            literal: Literal::None,
-            line: self.line,
-        });
-        self.tokens.clone()
-    }
-
-    fn is_at_end(&self) -> bool {
-        self.current >= self.source.len()
-    }
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        path: String::from("lox_sample/lox.txt"),
+        color: io::stdout().is_terminal(),
+        config: Config {
+            dump: Dump::Debug,
+            max_string_length: None,
+        },
+    };
 
-    fn scan_token(&mut self) {
-        let c = self.advance();
-        match c {
-            '(' => self.add_token(TokenType::LeftParen, Literal::None),
-            ')' => self.add_token(TokenType::RightParen, Literal::None),
-            '{' => self.add_token(TokenType::LeftBrace, Literal::None),
-            '}' => self.add_token(TokenType::RightBrace, Literal::None),
-            ',' => self.add_token(TokenType::Comma, Literal::None),
-            '.' => self.add_token(TokenType::Dot, Literal::None),
-            '-' => self.add_token(TokenType::Minus, Literal::None),
-            '+' => self.add_token(TokenType::Plus, Literal::None),
-            ';' => self.add_token(TokenType::Semicolon, Literal::None),
-            '*' => self.add_token(TokenType::Star, Literal::None),
-            '!' => {
-                if self.match_token('=') {
-                    self.add_token(TokenType::BangEqual, Literal::None);
-                } else {
-                    self.add_token(TokenType::Bang, Literal::None)
-                }
-            }
-            '=' => {
-                if self.match_token('=') {
-                    self.add_token(TokenType::EqualEqual, Literal::None);
-                } else {
-                    self.add_token(TokenType::Equal, Literal::None)
-                }
-            }
-            '<' => {
-                if self.match_token('=') {
-                    self.add_token(TokenType::LessEqual, Literal::None);
-                } else {
-                    self.add_token(TokenType::Less, Literal::None)
-                }
-            }
-            '>' => {
-                if self.match_token('=') {
-                    self.add_token(TokenType::GreaterEqual, Literal::None);
-                } else {
-                    self.add_token(TokenType::Greater, Literal::None)
-                }
-            }
-            '/' => {
-                if self.match_token('/') {
-                    while self.peek() != '\n' && !self.is_at_end() {
-                        self.advance();
-                    }
-                } else {
-                    self.add_token(TokenType::Slash, Literal::None)
-                }
-            }
-            ' ' => (),
-            '\r' => (),
-            '\t' => (),
-            '\n' => self.line += 1,
-            '"' => self.scan_string(),
+    for arg in args {
+        match arg.as_str() {
+            "--no-color" => options.color = false,
+            "--dump-tokens-pretty" => options.config.dump = Dump::Pretty(DEFAULT_LEXEME_WIDTH),
+            "--annotate" => options.config.dump = Dump::Annotate,
             _ => {
-                if self.is_digit(c) {
-                    self.number();
-                } else if self.is_alpha(c) {
-                    self.identifier();
+                if let Some(width) = arg.strip_prefix("--dump-tokens-pretty=") {
+                    options.config.dump = Dump::Pretty(parse_count(width, "--dump-tokens-pretty")?);
+                } else if let Some(length) = arg.strip_prefix("--max-string-length=") {
+                    options.config.max_string_length =
+                        Some(parse_count(length, "--max-string-length")?);
                 } else {
-                    unsafe { LOX.error(self.line, "Unexpected character.") }
+                    options.path = arg;
                 }
             }
         }
     }
 
-    fn advance(&mut self) -> char {
-        let c = self.source.chars().nth(self.current).unwrap();
-        self.current += 1;
-
-        c
-    }
-
-    fn match_token(&mut self, expected: char) -> bool {
-        if self.is_at_end() {
-            return false;
-        }
-
-        if self.source.chars().nth(self.current).unwrap() != expected {
-            return false;
-        }
-
-        self.current += 1;
-        true
-    }
-
-    fn peek(&self) -> char {
-        if self.is_at_end() {
-            return '\0';
-        }
+    Ok(options)
+}
 
-        self.source.chars().nth(self.current).unwrap()
-    }
+fn parse_count(value: &str, flag: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}; expected a number.", value, flag))
+}
 
-    fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
-            return '\0';
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(64);
         }
+    };
 
-        self.source.chars().nth(self.current + 1).unwrap()
+    Lox::set_color(options.color);
+    if let Err(message) = read_file(&options.path, &options.config) {
+        eprintln!("{}", message);
+        process::exit(66);
     }
+}
 
-    fn scan_string(&mut self) {
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
-            }
-            self.advance();
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if self.is_at_end() {
-            unsafe {
-                LOX.error(self.line, "Unterminated string.");
-            }
-            return;
-        }
-
-        let value = self.source[self.start + 1..self.current - 1].to_string();
-        self.add_token(TokenType::String, Literal::String(value));
+    fn args(list: &[&str]) -> impl Iterator<Item = String> {
+        list.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
     }
 
-    fn is_digit(&self, c: char) -> bool {
-        c >= '0' && c <= '9'
+    #[test]
+    fn no_color_turns_color_off() {
+        let options = parse_args(args(&["--no-color", "file.lox"])).unwrap();
+        assert!(!options.color);
+        assert_eq!(options.path, "file.lox");
     }
 
-    fn number(&mut self) {
-        let mut float_num = false;
-        while self.is_digit(self.peek()) {
-            self.advance();
-        }
-
-        if self.peek() == '.' && self.is_digit(self.peek_next()) {
-            float_num = true;
-            self.advance();
-
-            while self.is_digit(self.peek()) {
-                self.advance();
-            }
-        }
-
-        let value = self.source[self.start..self.current].to_string();
-
-        if float_num {
-            self.add_token(
-                TokenType::Number,
-                Literal::Float(value.trim().parse::<f32>().unwrap()),
-            );
-        } else {
-            self.add_token(
-                TokenType::Number,
-                Literal::Integer(value.trim().parse::<i32>().unwrap()),
-            );
-        }
+    #[test]
+    fn max_string_length_flag() {
+        let options = parse_args(args(&["--max-string-length=8"])).unwrap();
+        assert_eq!(options.config.max_string_length, Some(8));
     }
 
-    fn is_alpha(&self, c: char) -> bool {
-        (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || c == '_'
+    #[test]
+    fn bad_width_is_a_usage_error() {
+        let error = parse_args(args(&["--dump-tokens-pretty=abc"]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error,
+            "Invalid value 'abc' for --dump-tokens-pretty; expected a number."
+        );
     }
 
-    fn is_alpah_numeric(&self, c: char) -> bool {
-        self.is_alpha(c) || self.is_digit(c)
-    }
-
-    fn identifier(&mut self) {
-        while self.is_alpah_numeric(self.peek()) {
-            self.advance();
-        }
-
-        let text = self.source[self.start..self.current].to_string();
-
-        match self.keywords.get(text.as_str()) {
-            Some(t) => self.add_token(t.clone(), Literal::None),
-            None => self.add_token(TokenType::Identifier, Literal::String(text)),
+    fn config() -> Config {
+        Config {
+            dump: Dump::Debug,
+            max_string_length: None,
         }
     }
 
-    fn add_token(&mut self, token_type: TokenType, literal: Literal) {
-        let text = self.source[self.start..self.current].to_string();
-        self.tokens.push(Token {
-            token_type,
-            lexeme: text,
-            literal,
-            line: self.line,
-        });
+    #[test]
+    fn missing_file_is_reported() {
+        let error = read_file("no/such/file.lox", &config()).unwrap_err();
+        assert_eq!(error, "Could not open file 'no/such/file.lox'.");
     }
-}
-
-#[derive(Debug, Clone)]
-enum Literal {
-    Integer(i32),
-    Float(f32),
-    String(String),
-    None,
-}
-
-#[derive(Debug, Clone)]
-struct Token {
-    token_type: TokenType,
-    lexeme: String,
-    literal: Literal, // Object?
-    line: usize,
-}
-
-#[derive(Debug, Clone)]
-enum TokenType {
-    // Single-character tokens.
-    LeftParen,
-    RightParen,
-    LeftBrace,
-    RightBrace,
-    Comma,
-    Dot,
-    Minus,
-    Plus,
-    Semicolon,
-    Slash,
-    Star,
-
-    // One or two character tokens.
-    Bang,
-    BangEqual,
-    Equal,
-    EqualEqual,
-    Greater,
-    GreaterEqual,
-    Less,
-    LessEqual,
-
-    // Literals.
-    Identifier,
-    String,
-    Number,
-
-    // Keywords.
-    And,
-    Class,
-    Else,
-    False,
-    Fun,
-    For,
-    If,
-    Nil,
-    Or,
-    Print,
-    Return,
-    Super,
-    This,
-    True,
-    Var,
-    While,
-
-    Eof,
-}
-
-fn run(source: String) {
-    let keywords = HashMap::from([
-        ("false", TokenType::False),
-        ("for", TokenType::For),
-        ("fun", TokenType::Fun),
-        ("if", TokenType::If),
-        ("nil", TokenType::Nil),
-        ("or", TokenType::Or),
-        ("print", TokenType::Print),
-        ("return", TokenType::Return),
-        ("super", TokenType::Super),
-        ("this", TokenType::This),
-        ("true", TokenType::True),
-        ("var", TokenType::Var),
-        ("while", TokenType::While),
-    ]);
 
-    let mut scanner = Scanner {
-        source,
-        tokens: Vec::new(),
-        start: 0,
-        current: 0,
-        line: 1,
-        keywords,
-    };
+    #[test]
+    fn non_utf8_file_is_reported() {
+        let path = env::temp_dir().join(format!("rlox-{}-latin1.lox", process::id()));
+        fs::write(&path, b"print \"caf\xe9\";").unwrap();
+        let path = path.to_str().unwrap().to_string();
 
-    let tokens = scanner.scan_tokens();
-    for token in tokens.iter() {
-        println!("{:?}", token);
+        let error = read_file(&path, &config()).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            error,
+            format!("File '{}' is not valid UTF-8 (at byte 10).", path)
+        );
     }
 }
-
-fn read_file(path: String) -> Result<(), Box<dyn Error>> {
-    let source = fs::read_to_string(path)?.parse()?;
-    run(source);
-
-    Ok(())
-}
-
-fn main() -> Result<(), Box<dyn Error>> {
-    let res = read_file(String::from("lox_sample/lox.txt"));
-
-    res
-}
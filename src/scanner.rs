@@ -0,0 +1,1406 @@
+use std::{
+    io::{self, BufRead},
+    num::IntErrorKind,
+    ops::Range,
+    rc::Rc,
+};
+
+use crate::error::LoxError;
+use crate::intern::{Interner, Symbol};
+use crate::token::{Literal, Token, TokenType};
+
+const DEFAULT_MAX_ERRORS: usize = 100;
+const DEFAULT_MAX_STRING_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Scans `source` in one go, returning the tokens and any errors found.
+pub fn scan_all(source: &str) -> (Vec<Token>, Vec<LoxError>) {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens();
+    (tokens, scanner.errors)
+}
+
+/// Where a line starts and what the scanner had produced by then, so
+/// `rescan` can restart there without looking at the tokens themselves.
+#[derive(Debug, Clone, Copy)]
+struct LineStart {
+    offset: usize,
+    tokens: usize,
+    errors: usize,
+    paren_depth: usize,
+    // False when the line starts inside a string literal.
+    clean: bool,
+}
+
+pub struct Scanner {
+    source: String,
+    tokens: Vec<Token>,
+    line_starts: Vec<LineStart>,
+
+    start: usize,
+    current: usize,
+    line: usize,
+    column: usize,
+    start_column: usize,
+
+    interner: Interner,
+
+    significant_newlines: bool,
+    paren_depth: usize,
+
+    // Set while reading from a stream that may still extend the buffer.
+    awaiting_input: bool,
+    incomplete: bool,
+    // Inside an overlong string whose text is dropped as it is read. Holds
+    // whether the string is raw.
+    skipping_string: Option<bool>,
+    // The last scan came from scan_reader, which drops the scanned input.
+    streamed: bool,
+
+    errors: Vec<LoxError>,
+    max_errors: usize,
+    aborted: bool,
+    max_string_length: usize,
+}
+
+impl Scanner {
+    pub fn new(source: String) -> Self {
+        let mut scanner = Scanner {
+            source: String::new(),
+            tokens: Vec::new(),
+            line_starts: Vec::new(),
+            start: 0,
+            current: 0,
+            line: 1,
+            column: 0,
+            start_column: 0,
+            interner: Interner::default(),
+            significant_newlines: false,
+            paren_depth: 0,
+            awaiting_input: false,
+            incomplete: false,
+            skipping_string: None,
+            streamed: false,
+            errors: Vec::new(),
+            max_errors: DEFAULT_MAX_ERRORS,
+            aborted: false,
+            max_string_length: DEFAULT_MAX_STRING_LENGTH,
+        };
+        scanner.reset(source);
+        scanner
+    }
+
+    /// Tokens collected by the last scan, borrowed rather than cloned.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Errors found by the last scan, in source order.
+    pub fn errors(&self) -> &[LoxError] {
+        &self.errors
+    }
+
+    /// Stops scanning after this many errors. Defaults to 100.
+    pub fn set_max_errors(&mut self, max_errors: usize) {
+        self.max_errors = max_errors;
+    }
+
+    /// Rejects string literals longer than this many bytes. Defaults to 64 MiB.
+    pub fn set_max_string_length(&mut self, max_string_length: usize) {
+        self.max_string_length = max_string_length;
+    }
+
+    /// Emits `Newline` tokens at line ends outside parentheses, for
+    /// experimenting with automatic semicolon insertion. Off by default.
+    pub fn set_significant_newlines(&mut self, enabled: bool) {
+        self.significant_newlines = enabled;
+    }
+
+    /// The source being scanned. It stays owned by the scanner after
+    /// `scan_tokens`, so callers can show context without re-reading it.
+    /// `scan_reader` doesn't keep its input, so after it this is empty.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The interner holding every identifier and keyword scanned so far.
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    /// Prepares the scanner for a new source, keeping the interned symbols.
+    pub fn reset(&mut self, source: String) {
+        self.source = source;
+        self.tokens.clear();
+        self.line_starts.clear();
+        self.start = 0;
+        self.current = 0;
+        self.line = 1;
+        self.column = 0;
+        self.start_column = 0;
+        self.paren_depth = 0;
+        self.awaiting_input = false;
+        self.incomplete = false;
+        self.skipping_string = None;
+        self.streamed = false;
+        self.errors.clear();
+        self.aborted = false;
+        self.mark_line_start(true);
+    }
+
+    pub fn scan_tokens(&mut self) -> Vec<Token> {
+        self.scan_to_end();
+        self.tokens.clone()
+    }
+
+    fn scan_to_end(&mut self) {
+        while !self.is_at_end() {
+            self.start = self.current;
+            self.start_column = self.column;
+            self.scan_token()
+        }
+
+        self.add_eof();
+    }
+
+    /// Scans `reader` a line at a time, keeping only the unscanned part of
+    /// the input in memory. A string spanning several lines is held back
+    /// until its closing quote arrives. Produces the same tokens as scanning
+    /// the whole input as one `String`.
+    ///
+    /// The input is not retained. Afterwards `source` is empty, `rescan`
+    /// panics, and the tokens can't be passed to `dump::annotate`.
+    pub fn scan_reader<R: BufRead>(&mut self, reader: R) -> io::Result<Vec<Token>> {
+        self.reset(String::new());
+        self.streamed = true;
+
+        // Don't leave the streaming flags set, or the next scan would treat
+        // an unterminated string as waiting for more input.
+        let result = self.scan_lines(reader);
+        self.awaiting_input = false;
+        self.incomplete = false;
+        self.skipping_string = None;
+        result?;
+
+        self.add_eof();
+        Ok(self.tokens.clone())
+    }
+
+    fn scan_lines<R: BufRead>(&mut self, mut reader: R) -> io::Result<()> {
+        loop {
+            self.awaiting_input = reader.read_line(&mut self.source)? > 0;
+
+            if let Some(raw) = self.skipping_string.take() {
+                self.skip_string(raw, false);
+            }
+
+            while !self.is_at_end() {
+                let (line, column) = (self.line, self.column);
+                self.start = self.current;
+                self.start_column = self.column;
+                self.scan_token();
+
+                if self.incomplete {
+                    self.incomplete = false;
+                    self.current = self.start;
+                    self.line = line;
+                    self.column = column;
+                    break;
+                }
+            }
+
+            self.source.drain(..self.current);
+            self.current = 0;
+
+            if !self.awaiting_input || self.aborted {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Replaces `edit` in the source of the last scan with `replacement` and
+    /// scans the result, reusing the previous tokens and errors outside the
+    /// lines the edit touches. Offsets are character indices into the old
+    /// source. The result is the same as a full scan of the edited source.
+    ///
+    /// Nothing is reused when nothing has been scanned since `new` or
+    /// `reset`, or when the last scan hit the error limit; the edited source
+    /// is then scanned in full.
+    ///
+    /// Offsets past the end of the source are clamped to its end.
+    ///
+    /// # Panics
+    ///
+    /// If `edit` is reversed, or if the last scan was `scan_reader`, which
+    /// doesn't keep the source.
+    pub fn rescan(&mut self, edit: Range<usize>, replacement: &str) -> &[Token] {
+        assert!(edit.start <= edit.end, "reversed edit range {:?}", edit);
+        assert!(
+            !self.streamed,
+            "rescan needs the whole source, which scan_reader doesn't keep"
+        );
+        let len = self.source.len();
+        let mut offsets = self.source.char_indices().map(|(offset, _)| offset);
+        let start = offsets.nth(edit.start).unwrap_or(len);
+        let end = match edit.len() {
+            0 => start,
+            n => offsets.nth(n - 1).unwrap_or(len),
+        };
+        let edit = start..end;
+        self.source.replace_range(edit.clone(), replacement);
+
+        // The Eof token is missing until a scan has run to the end.
+        if self.aborted || self.tokens.is_empty() {
+            return self.rescan_in_full();
+        }
+
+        // Back up to the nearest line at or before the edit that doesn't
+        // start inside a string. The first line always qualifies.
+        let first = self.line_starts.partition_point(|l| l.offset <= edit.start) - 1;
+        let restart = (0..=first)
+            .rev()
+            .find(|&i| self.line_starts[i].clean)
+            .unwrap();
+        let from = self.line_starts[restart];
+
+        // Set the old results from the restart line on aside, to be moved
+        // back once the scan catches up with them. old_lines[i] is the start
+        // of line restart + 1 + i.
+        let mut old_tokens = self.tokens.split_off(from.tokens);
+        let mut old_errors = self.errors.split_off(from.errors);
+        let mut old_lines = self.line_starts.split_off(restart);
+        self.line_starts.push(from);
+
+        self.current = from.offset;
+        self.line = restart + 1;
+        self.column = 0;
+        self.paren_depth = from.paren_depth;
+
+        let shift = replacement.len() as isize - edit.len() as isize;
+        let edit_end = edit.start + replacement.len();
+        // Whether a newline here would emit a Newline token.
+        let pending_newline =
+            |token: Option<&Token>| token.is_some_and(|t| t.token_type != TokenType::Newline);
+        let pending_before_restart = pending_newline(self.tokens.last());
+
+        while !self.is_at_end() {
+            self.start = self.current;
+            self.start_column = self.column;
+            self.scan_token();
+
+            // Past the edit the rest of the source is unchanged, so once a
+            // line starts here in the same state as it did in the old scan,
+            // the old results from that line on can be reused.
+            let here = *self.line_starts.last().unwrap();
+            if self.aborted || here.offset != self.current || self.current < edit_end {
+                continue;
+            }
+            let old_offset = (self.current as isize - shift) as usize;
+            let Ok(i) = old_lines.binary_search_by_key(&old_offset, |l| l.offset) else {
+                continue;
+            };
+            let old = old_lines[i];
+            let skip = old.tokens - from.tokens;
+            let old_pending = match skip {
+                0 => pending_before_restart,
+                _ => pending_newline(Some(&old_tokens[skip - 1])),
+            };
+            if !(here.clean && old.clean)
+                || here.paren_depth != old.paren_depth
+                || pending_newline(self.tokens.last()) != old_pending
+            {
+                continue;
+            }
+
+            let delta = self.line as isize - (restart + 1 + i) as isize;
+            let shift_line = |line: usize| (line as isize + delta) as usize;
+            self.tokens
+                .extend(old_tokens.drain(skip..).map(|mut token| {
+                    token.line = shift_line(token.line);
+                    token
+                }));
+            self.errors.extend(
+                old_errors
+                    .drain(old.errors - from.errors..)
+                    .map(|mut error| {
+                        error.line = shift_line(error.line);
+                        error
+                    }),
+            );
+            self.line_starts
+                .extend(old_lines.drain(i + 1..).map(|mut line| {
+                    line.offset = (line.offset as isize + shift) as usize;
+                    line.tokens = line.tokens - old.tokens + here.tokens;
+                    line.errors = line.errors - old.errors + here.errors;
+                    line
+                }));
+
+            // A full scan would have stopped at the error limit somewhere in
+            // the reused part.
+            if self.errors.len() >= self.max_errors {
+                return self.rescan_in_full();
+            }
+
+            let eof = self.tokens.last().unwrap();
+            self.line = eof.line;
+            self.column = eof.column - 1;
+            self.current = self.source.len();
+            return &self.tokens;
+        }
+
+        self.add_eof();
+        &self.tokens
+    }
+
+    fn rescan_in_full(&mut self) -> &[Token] {
+        let source = std::mem::take(&mut self.source);
+        self.reset(source);
+        self.scan_to_end();
+        &self.tokens
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.aborted || self.current >= self.source.len()
+    }
+
+    fn scan_token(&mut self) {
+        let c = self.advance();
+        match c {
+            '(' => {
+                self.paren_depth += 1;
+                self.add_token(TokenType::LeftParen, Literal::None)
+            }
+            ')' => {
+                self.paren_depth = self.paren_depth.saturating_sub(1);
+                self.add_token(TokenType::RightParen, Literal::None)
+            }
+            '{' => self.add_token(TokenType::LeftBrace, Literal::None),
+            '}' => self.add_token(TokenType::RightBrace, Literal::None),
+            ',' => self.add_token(TokenType::Comma, Literal::None),
+            '.' if self.peek().is_some_and(|c| self.is_digit(c)) => self.number(true),
+            '.' => self.add_token(TokenType::Dot, Literal::None),
+            '-' => self.add_token(TokenType::Minus, Literal::None),
+            '+' => self.add_token(TokenType::Plus, Literal::None),
+            ';' => self.add_token(TokenType::Semicolon, Literal::None),
+            '*' => self.add_token(TokenType::Star, Literal::None),
+            '!' => {
+                if self.match_token('=') {
+                    self.add_token(TokenType::BangEqual, Literal::None);
+                } else {
+                    self.add_token(TokenType::Bang, Literal::None)
+                }
+            }
+            '=' => {
+                if self.match_token('=') {
+                    self.add_token(TokenType::EqualEqual, Literal::None);
+                } else {
+                    self.add_token(TokenType::Equal, Literal::None)
+                }
+            }
+            '<' => {
+                if self.match_token('=') {
+                    self.add_token(TokenType::LessEqual, Literal::None);
+                } else {
+                    self.add_token(TokenType::Less, Literal::None)
+                }
+            }
+            '>' => {
+                if self.match_token('=') {
+                    self.add_token(TokenType::GreaterEqual, Literal::None);
+                } else {
+                    self.add_token(TokenType::Greater, Literal::None)
+                }
+            }
+            '&' => {
+                if self.match_token('&') {
+                    self.add_token(TokenType::And, Literal::None);
+                } else {
+                    self.error("Expected '&&'.")
+                }
+            }
+            '|' => {
+                if self.match_token('|') {
+                    self.add_token(TokenType::Or, Literal::None);
+                } else {
+                    self.error("Expected '||'.")
+                }
+            }
+            '/' => {
+                if self.match_token('/') {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.advance();
+                    }
+                } else {
+                    self.add_token(TokenType::Slash, Literal::None)
+                }
+            }
+            ' ' => (),
+            '\r' => (),
+            '\t' => (),
+            '\n' => {
+                self.newline();
+                self.line += 1;
+                self.mark_line_start(true);
+            }
+            '"' => self.scan_string(false),
+            'r' if self.peek() == Some('"') => {
+                self.advance();
+                self.scan_string(true)
+            }
+            _ => {
+                if self.is_digit(c) {
+                    self.number(false);
+                } else if self.is_alpha(c) {
+                    self.identifier();
+                } else {
+                    self.error("Unexpected character.")
+                }
+            }
+        }
+    }
+
+    // `current` is a byte offset, so every cursor method decodes at most a
+    // couple of characters instead of walking the source from the start.
+    fn advance(&mut self) -> char {
+        let c = self.peek().unwrap();
+        self.current += c.len_utf8();
+        if c == '\n' {
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+
+        c
+    }
+
+    fn match_token(&mut self, expected: char) -> bool {
+        if self.peek() != Some(expected) {
+            return false;
+        }
+
+        self.advance();
+        true
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        self.peek_at(1)
+    }
+
+    /// Looks `offset` characters past the cursor without consuming. `None`
+    /// past the end of the source.
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.source[self.current..].chars().nth(offset)
+    }
+
+    /// Raw strings (`r"..."`) keep backslashes as written and end at the
+    /// first quote.
+    fn scan_string(&mut self, raw: bool) {
+        let open = self.current;
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            if c == '"' && !escaped {
+                break;
+            }
+            // Checked as we go, so scan_reader never buffers more than the
+            // limit while waiting for the closing quote.
+            if self.current + c.len_utf8() - open > self.max_string_length {
+                self.error("String literal too long.");
+                if !self.aborted {
+                    self.skip_string(raw, escaped);
+                }
+                return;
+            }
+            escaped = !raw && !escaped && c == '\\';
+            self.advance_in_string();
+        }
+
+        if self.is_at_end() {
+            if self.awaiting_input {
+                self.incomplete = true;
+            } else {
+                self.error("Unterminated string.");
+            }
+            return;
+        }
+
+        // The closing ".
+        self.advance();
+
+        let body = &self.source[open..self.current - 1];
+        let value = if raw {
+            Ok(body.to_string())
+        } else {
+            unescape(body)
+        };
+        match value {
+            Ok(value) => self.add_token(TokenType::String, Literal::String(value)),
+            Err(message) => self.error(message),
+        }
+    }
+
+    /// Consumes the rest of an overlong string, closing quote included. When
+    /// the input runs out first, scan_reader resumes skipping on the next
+    /// line instead of holding the text back.
+    fn skip_string(&mut self, raw: bool, mut escaped: bool) {
+        while let Some(c) = self.peek() {
+            if c == '"' && !escaped {
+                self.advance();
+                return;
+            }
+            escaped = !raw && !escaped && c == '\\';
+            self.advance_in_string();
+        }
+
+        if self.awaiting_input {
+            self.skipping_string = Some(raw);
+        } else {
+            self.error("Unterminated string.");
+        }
+    }
+
+    fn advance_in_string(&mut self) {
+        if self.advance() == '\n' {
+            self.line += 1;
+            self.mark_line_start(false);
+        }
+    }
+
+    fn is_digit(&self, c: char) -> bool {
+        c.is_ascii_digit()
+    }
+
+    /// `.5` is a number, but `5.` is `5` followed by a `Dot`, so `5..m()`
+    /// scans as `5`, `.`, `.`, `m`. A fraction needs a digit after the dot.
+    fn number(&mut self, leading_dot: bool) {
+        if self.source[self.start..].starts_with("0o") {
+            return self.octal();
+        }
+
+        // A leading zero doesn't change the base: 017 is seventeen.
+        let mut float_num = leading_dot;
+        while self.peek().is_some_and(|c| self.is_digit(c)) {
+            self.advance();
+        }
+
+        if !leading_dot
+            && self.peek() == Some('.')
+            && self.peek_next().is_some_and(|c| self.is_digit(c))
+        {
+            float_num = true;
+            self.advance();
+
+            while self.peek().is_some_and(|c| self.is_digit(c)) {
+                self.advance();
+            }
+        }
+
+        let value = &self.source[self.start..self.current];
+
+        if float_num {
+            let value = value.parse::<f32>().unwrap();
+            self.add_token(TokenType::Number, Literal::Float(value));
+        } else {
+            // Only digits were consumed, so overflow is the one way to fail.
+            match value.parse::<i32>() {
+                Ok(value) => self.add_token(TokenType::Number, Literal::Integer(value)),
+                Err(_) => self.error("Number literal out of range."),
+            }
+        }
+    }
+
+    fn octal(&mut self) {
+        self.advance();
+        // Consume the whole alphanumeric run so a bad digit doesn't split it.
+        while self.peek().is_some_and(|c| self.is_alpah_numeric(c)) {
+            self.advance();
+        }
+
+        let digits = &self.source[self.start + 2..self.current];
+        match i32::from_str_radix(digits, 8) {
+            Ok(value) => self.add_token(TokenType::Number, Literal::Integer(value)),
+            Err(e) if *e.kind() == IntErrorKind::PosOverflow => {
+                self.error("Number literal out of range.")
+            }
+            Err(_) => self.error("Invalid octal literal."),
+        }
+    }
+
+    fn is_alpha(&self, c: char) -> bool {
+        c.is_ascii_lowercase() || c.is_ascii_uppercase() || c == '_'
+    }
+
+    fn is_alpah_numeric(&self, c: char) -> bool {
+        self.is_alpha(c) || self.is_digit(c)
+    }
+
+    fn identifier(&mut self) {
+        while self.peek().is_some_and(|c| self.is_alpah_numeric(c)) {
+            self.advance();
+        }
+
+        let (symbol, text) = self.interner.intern(&self.source[self.start..self.current]);
+
+        match keyword(&text) {
+            Some(t) => {
+                let literal = match t {
+                    TokenType::True => Literal::Boolean(true),
+                    TokenType::False => Literal::Boolean(false),
+                    TokenType::Nil => Literal::Nil,
+                    _ => Literal::None,
+                };
+                self.push_token(t, text, literal, Some(symbol))
+            }
+            // The name is already in the lexeme and symbol, so don't copy it
+            // into the literal as well.
+            None => self.push_token(TokenType::Identifier, text, Literal::None, Some(symbol)),
+        }
+    }
+
+    fn error(&mut self, message: &str) {
+        self.errors.push(LoxError {
+            line: self.line,
+            message: message.to_string(),
+        });
+
+        if self.errors.len() >= self.max_errors {
+            self.errors.push(LoxError {
+                line: self.line,
+                message: "Too many errors; aborting.".to_string(),
+            });
+            self.aborted = true;
+        }
+    }
+
+    fn mark_line_start(&mut self, clean: bool) {
+        self.line_starts.push(LineStart {
+            offset: self.current,
+            tokens: self.tokens.len(),
+            errors: self.errors.len(),
+            paren_depth: self.paren_depth,
+            clean,
+        });
+    }
+
+    fn newline(&mut self) {
+        if !self.significant_newlines || self.paren_depth > 0 {
+            return;
+        }
+
+        // One Newline per run of blank lines, none before the first token.
+        match self.tokens.last() {
+            None => (),
+            Some(token) if token.token_type == TokenType::Newline => (),
+            Some(_) => self.add_token(TokenType::Newline, Literal::None),
+        }
+    }
+
+    fn add_eof(&mut self) {
+        self.start_column = self.column;
+        self.push_token(TokenType::Eof, Rc::from(""), Literal::None, None);
+    }
+
+    fn add_token(&mut self, token_type: TokenType, literal: Literal) {
+        let text = Rc::from(&self.source[self.start..self.current]);
+        self.push_token(token_type, text, literal, None);
+    }
+
+    fn push_token(
+        &mut self,
+        token_type: TokenType,
+        lexeme: Rc<str>,
+        literal: Literal,
+        symbol: Option<Symbol>,
+    ) {
+        self.tokens.push(Token {
+            token_type,
+            lexeme,
+            literal,
+            line: self.line,
+            column: self.start_column + 1,
+            symbol,
+        });
+    }
+}
+
+fn keyword(text: &str) -> Option<TokenType> {
+    let token_type = match text {
+        "and" => TokenType::And,
+        "class" => TokenType::Class,
+        "else" => TokenType::Else,
+        "false" => TokenType::False,
+        "for" => TokenType::For,
+        "fun" => TokenType::Fun,
+        "if" => TokenType::If,
+        "nil" => TokenType::Nil,
+        "or" => TokenType::Or,
+        "print" => TokenType::Print,
+        "return" => TokenType::Return,
+        "super" => TokenType::Super,
+        "this" => TokenType::This,
+        "true" => TokenType::True,
+        "var" => TokenType::Var,
+        "while" => TokenType::While,
+        _ => return None,
+    };
+    Some(token_type)
+}
+
+fn unescape(body: &str) -> Result<String, &'static str> {
+    let mut value = String::with_capacity(body.len());
+    let mut chars = body.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        value.push(match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('/') => '/',
+            // Bytes above 0x7f are taken as the Latin-1 code points U+0080..U+00FF.
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if hex.len() != 2 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err("Invalid \\x escape; expected two hex digits.");
+                }
+                char::from(u8::from_str_radix(&hex, 16).unwrap())
+            }
+            _ => return Err("Invalid escape sequence."),
+        });
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_identifiers_share_a_symbol() {
+        let (tokens, _) = scan_all("foo bar foo");
+        assert_eq!(tokens[0].symbol, tokens[2].symbol);
+        assert_ne!(tokens[0].symbol, tokens[1].symbol);
+        assert!(Rc::ptr_eq(&tokens[0].lexeme, &tokens[2].lexeme));
+        assert_eq!(tokens[0].literal, Literal::None);
+    }
+
+    #[test]
+    fn scan_reader_stops_at_max_errors() {
+        let mut scanner = Scanner::new(String::new());
+        let source = "@\n".repeat(1000);
+        let tokens = scanner.scan_reader(source.as_bytes()).unwrap();
+
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(scanner.errors().len(), DEFAULT_MAX_ERRORS + 1);
+        let last = scanner.errors().last().unwrap();
+        assert_eq!(last.message, "Too many errors; aborting.");
+        assert_eq!(last.line, DEFAULT_MAX_ERRORS);
+        assert!(!scanner.awaiting_input);
+    }
+
+    #[test]
+    fn scan_reader_matches_scan_tokens() {
+        let source = "var a = \"one\ntwo\";\n// note\nprint a + 1.5;\n\"open";
+        let expected = scan_all(source);
+
+        let mut scanner = Scanner::new(String::new());
+        let tokens = scanner.scan_reader(io::Cursor::new(source)).unwrap();
+        assert_eq!(tokens, expected.0);
+        assert_eq!(
+            scanner
+                .errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>(),
+            expected.1.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reset_after_aborted_scan_reader_reports_errors() {
+        let mut scanner = Scanner::new(String::new());
+        scanner.set_max_errors(1);
+        scanner.scan_reader("@\n@\n".as_bytes()).unwrap();
+
+        scanner.reset("\"unterminated".to_string());
+        let tokens = scanner.scan_tokens();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(scanner.errors()[0].message, "Unterminated string.");
+    }
+
+    fn types(tokens: &[Token]) -> Vec<TokenType> {
+        tokens.iter().map(|t| t.token_type.clone()).collect()
+    }
+
+    fn messages(errors: &[LoxError]) -> Vec<String> {
+        errors.iter().map(|e| e.to_string()).collect()
+    }
+
+    // Symbol ids depend on what the interner saw before, so compare tokens
+    // by the names their symbols resolve to.
+    fn resolved(scanner: &Scanner, tokens: Vec<Token>) -> Vec<(Token, Option<String>)> {
+        tokens
+            .into_iter()
+            .map(|token| {
+                let name = token
+                    .symbol
+                    .map(|s| scanner.interner().resolve(s).to_string());
+                (
+                    Token {
+                        symbol: None,
+                        ..token
+                    },
+                    name,
+                )
+            })
+            .collect()
+    }
+
+    fn full_scan(
+        source: &str,
+        configure: impl Fn(&mut Scanner),
+    ) -> (Vec<(Token, Option<String>)>, Vec<String>) {
+        let mut scanner = Scanner::new(source.to_string());
+        configure(&mut scanner);
+        let tokens = scanner.scan_tokens();
+        (resolved(&scanner, tokens), messages(scanner.errors()))
+    }
+
+    #[test]
+    fn rescan_after_string_dropped_with_error() {
+        let old = "\"\\q\nx\"\ny";
+        let mut scanner = Scanner::new(old.to_string());
+        scanner.scan_tokens();
+
+        let tokens = scanner.rescan(4..5, "z").to_vec();
+        let (expected, errors) = full_scan("\"\\q\nz\"\ny", |_| ());
+        assert_eq!(resolved(&scanner, tokens), expected);
+        assert_eq!(messages(scanner.errors()), errors);
+    }
+
+    #[test]
+    fn rescan_keeps_errors_outside_the_edit() {
+        let mut scanner = Scanner::new("@\na\n@\n".to_string());
+        scanner.scan_tokens();
+
+        scanner.rescan(2..3, "b\nc");
+        assert_eq!(
+            messages(scanner.errors()),
+            [
+                "[line: 1] Error : Unexpected character.",
+                "[line: 4] Error : Unexpected character."
+            ]
+        );
+    }
+
+    // Random single-character edits, several in a row on the same scanner,
+    // must give the same tokens and errors as scanning the result in full.
+    #[test]
+    fn rescan_matches_full_scan() {
+        const ALPHABET: &[char] = &[
+            'a', 'r', 'o', '0', '1', '.', '"', '\\', 'q', 'x', '\n', '\n', ' ', '(', ')', '/', '@',
+            '=',
+        ];
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = |n: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % n as u64) as usize
+        };
+
+        for case in 0..4000 {
+            let significant = case % 2 == 1;
+            let max_errors = if case % 3 == 0 { 3 } else { DEFAULT_MAX_ERRORS };
+            let max_string_length = if case % 5 == 0 {
+                4
+            } else {
+                DEFAULT_MAX_STRING_LENGTH
+            };
+            let configure = |scanner: &mut Scanner| {
+                scanner.set_significant_newlines(significant);
+                scanner.set_max_errors(max_errors);
+                scanner.set_max_string_length(max_string_length);
+            };
+
+            let len = next(24);
+            let mut source: Vec<char> = (0..len).map(|_| ALPHABET[next(ALPHABET.len())]).collect();
+            let mut scanner = Scanner::new(source.iter().collect());
+            configure(&mut scanner);
+            scanner.scan_tokens();
+
+            for _ in 0..5 {
+                let at = next(source.len() + 1);
+                let c = ALPHABET[next(ALPHABET.len())];
+                let (edit, replacement) = match next(3) {
+                    0 => (at..at, c.to_string()),
+                    _ if at == source.len() => (at..at, c.to_string()),
+                    1 => (at..at + 1, String::new()),
+                    _ => (at..at + 1, c.to_string()),
+                };
+                let old: String = source.iter().collect();
+                source.splice(edit.clone(), replacement.chars());
+                let new: String = source.iter().collect();
+
+                let tokens = scanner.rescan(edit.clone(), &replacement).to_vec();
+                let tokens = resolved(&scanner, tokens);
+                let (expected, errors) = full_scan(&new, configure);
+                assert_eq!(tokens, expected, "{:?} {:?} {:?}", old, edit, replacement);
+                assert_eq!(
+                    messages(scanner.errors()),
+                    errors,
+                    "{:?} {:?} {:?}",
+                    old,
+                    edit,
+                    replacement
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn raw_strings_keep_backslashes() {
+        let (tokens, errors) = scan_all(r#"r"C:\path" r"a\" "tab\t\"q\"""#);
+        let values: Vec<_> = tokens.iter().map(|t| t.literal.as_string()).collect();
+        assert_eq!(
+            values,
+            [Some(r"C:\path"), Some(r"a\"), Some("tab\t\"q\""), None]
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn unknown_escape_in_normal_string_is_an_error() {
+        let (tokens, errors) = scan_all(r#""C:\path""#);
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(errors[0].message, "Invalid escape sequence.");
+    }
+
+    #[test]
+    fn string_longer_than_limit_is_an_error() {
+        let mut scanner = Scanner::new("\"abc\" \"abcd\" x".to_string());
+        scanner.set_max_string_length(3);
+        let tokens = scanner.scan_tokens();
+
+        assert_eq!(
+            types(&tokens),
+            [TokenType::String, TokenType::Identifier, TokenType::Eof]
+        );
+        assert_eq!(
+            messages(scanner.errors()),
+            ["[line: 1] Error : String literal too long."]
+        );
+    }
+
+    #[test]
+    fn scan_reader_skips_overlong_strings() {
+        let source = "a \"one\ntwo\nthree\" b\n\"four\nfive";
+        let (expected, errors) = full_scan(source, |s| s.set_max_string_length(4));
+
+        let mut scanner = Scanner::new(String::new());
+        scanner.set_max_string_length(4);
+        let tokens = scanner.scan_reader(source.as_bytes()).unwrap();
+        assert_eq!(resolved(&scanner, tokens), expected);
+        assert_eq!(messages(scanner.errors()), errors);
+        assert_eq!(
+            errors,
+            [
+                "[line: 2] Error : String literal too long.",
+                "[line: 4] Error : String literal too long.",
+                "[line: 5] Error : Unterminated string.",
+            ]
+        );
+    }
+
+    #[test]
+    fn octal_and_decimal_literals() {
+        let (tokens, errors) = scan_all("0o17 017 0o 0o19 0o+1");
+        let values: Vec<_> = tokens.iter().map(|t| t.literal.clone()).collect();
+        assert_eq!(
+            values,
+            [
+                Literal::Integer(15),
+                Literal::Integer(17),
+                Literal::None,
+                Literal::Integer(1),
+                Literal::None,
+            ]
+        );
+        assert_eq!(
+            messages(&errors),
+            [
+                "[line: 1] Error : Invalid octal literal.",
+                "[line: 1] Error : Invalid octal literal.",
+                "[line: 1] Error : Invalid octal literal.",
+            ]
+        );
+    }
+
+    #[test]
+    fn integer_overflow_is_an_error() {
+        let (tokens, errors) = scan_all("99999999999 0o77777777777 2147483647");
+        assert_eq!(tokens[0].literal, Literal::Integer(i32::MAX));
+        assert_eq!(
+            messages(&errors),
+            [
+                "[line: 1] Error : Number literal out of range.",
+                "[line: 1] Error : Number literal out of range.",
+            ]
+        );
+    }
+
+    #[test]
+    fn scan_reader_clears_state_on_io_error() {
+        struct Failing;
+
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("boom"))
+            }
+        }
+
+        let mut scanner = Scanner::new(String::new());
+        assert!(scanner.scan_reader(io::BufReader::new(Failing)).is_err());
+        assert!(!scanner.awaiting_input);
+        assert!(!scanner.incomplete);
+    }
+
+    #[test]
+    fn nul_character_in_string_is_kept() {
+        let (tokens, errors) = scan_all("\"a\u{0}b\"");
+        assert_eq!(tokens[0].literal, Literal::String("a\u{0}b".to_string()));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn nul_character_in_code_is_unexpected() {
+        let (tokens, errors) = scan_all("a\u{0}b");
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(
+            messages(&errors),
+            ["[line: 1] Error : Unexpected character."]
+        );
+    }
+
+    #[test]
+    fn reset_scans_a_second_source() {
+        let mut scanner = Scanner::new("var a; @".to_string());
+        scanner.scan_tokens();
+        assert_eq!(scanner.errors().len(), 1);
+
+        scanner.reset("print\nb;".to_string());
+        let tokens = scanner.scan_tokens();
+        assert_eq!(
+            types(&tokens),
+            [
+                TokenType::Print,
+                TokenType::Identifier,
+                TokenType::Semicolon,
+                TokenType::Eof
+            ]
+        );
+        assert_eq!((tokens[1].line, tokens[1].column), (2, 1));
+        assert!(scanner.errors().is_empty());
+        assert_eq!(scanner.source(), "print\nb;");
+    }
+
+    #[test]
+    fn double_ampersand_and_bar_are_and_or() {
+        let (tokens, errors) = scan_all("a && b || c");
+        assert_eq!(
+            types(&tokens),
+            [
+                TokenType::Identifier,
+                TokenType::And,
+                TokenType::Identifier,
+                TokenType::Or,
+                TokenType::Identifier,
+                TokenType::Eof
+            ]
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn lone_ampersand_or_bar_is_an_error() {
+        let (tokens, errors) = scan_all("a & b | c");
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(
+            messages(&errors),
+            [
+                "[line: 1] Error : Expected '&&'.",
+                "[line: 1] Error : Expected '||'."
+            ]
+        );
+    }
+
+    #[test]
+    fn boolean_keywords_carry_their_value() {
+        let (tokens, _) = scan_all("true false");
+        assert_eq!(tokens[0].literal, Literal::Boolean(true));
+        assert_eq!(tokens[1].literal, Literal::Boolean(false));
+    }
+
+    #[test]
+    fn nil_literal_differs_from_no_literal() {
+        let (tokens, _) = scan_all("nil (");
+        assert_eq!(tokens[0].literal, Literal::Nil);
+        assert_eq!(tokens[1].literal, Literal::None);
+    }
+
+    #[test]
+    fn significant_newlines_outside_parens() {
+        let mut scanner = Scanner::new("\na\n\nb(\nc\n)\n".to_string());
+        scanner.set_significant_newlines(true);
+        let tokens = scanner.scan_tokens();
+        assert_eq!(
+            types(&tokens),
+            [
+                TokenType::Identifier,
+                TokenType::Newline,
+                TokenType::Identifier,
+                TokenType::LeftParen,
+                TokenType::Identifier,
+                TokenType::RightParen,
+                TokenType::Newline,
+                TokenType::Eof
+            ]
+        );
+        assert_eq!(tokens[1].line, 2);
+    }
+
+    #[test]
+    fn newlines_are_skipped_by_default() {
+        let (tokens, _) = scan_all("a\nb\n");
+        assert_eq!(
+            types(&tokens),
+            [TokenType::Identifier, TokenType::Identifier, TokenType::Eof]
+        );
+    }
+
+    #[test]
+    fn token_spans_slice_the_borrowed_source() {
+        let mut scanner = Scanner::new("var s = \"héllo\"; s = 1;\nprint s;".to_string());
+        scanner.scan_tokens();
+
+        let source = scanner.source();
+        for token in &scanner.tokens()[..scanner.tokens().len() - 1] {
+            let line = source.lines().nth(token.line - 1).unwrap();
+            let start = line.char_indices().nth(token.column - 1).unwrap().0;
+            let span = &line[start..start + token.lexeme.len()];
+            assert_eq!(span, &*token.lexeme);
+        }
+    }
+
+    #[test]
+    fn two_character_operators() {
+        let (tokens, _) = scan_all("==<= >=\n  != =");
+        let spans: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.token_type.clone(), t.line, t.column))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                (TokenType::EqualEqual, 1, 1),
+                (TokenType::LessEqual, 1, 3),
+                (TokenType::GreaterEqual, 1, 6),
+                (TokenType::BangEqual, 2, 3),
+                (TokenType::Equal, 2, 6),
+                (TokenType::Eof, 2, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn scan_all_returns_tokens_and_errors() {
+        let (tokens, errors) = scan_all("x @");
+        assert_eq!(types(&tokens), [TokenType::Identifier, TokenType::Eof]);
+        assert_eq!(
+            messages(&errors),
+            ["[line: 1] Error : Unexpected character."]
+        );
+    }
+
+    #[test]
+    fn peek_at_looks_ahead_without_consuming() {
+        let mut scanner = Scanner::new("aé\nb".to_string());
+        scanner.advance();
+        assert_eq!(scanner.peek_at(0), Some('é'));
+        assert_eq!(scanner.peek_at(1), Some('\n'));
+        assert_eq!(scanner.peek_at(2), Some('b'));
+        assert_eq!(scanner.peek_at(3), None);
+        assert_eq!(scanner.peek_at(100), None);
+        assert_eq!(scanner.peek(), Some('é'));
+    }
+
+    #[test]
+    fn tokens_borrows_the_last_scan() {
+        let mut scanner = Scanner::new("a b".to_string());
+        let scanned = scanner.scan_tokens();
+
+        let tokens = scanner.tokens();
+        assert_eq!(tokens, &scanned[..]);
+        assert_eq!(tokens[tokens.len() - 1].token_type, TokenType::Eof);
+    }
+
+    #[test]
+    fn number_forms_around_the_dot() {
+        let (tokens, _) = scan_all(".5 5. 5.0");
+        let values: Vec<_> = tokens.iter().map(|t| t.literal.clone()).collect();
+        assert_eq!(
+            values,
+            [
+                Literal::Float(0.5),
+                Literal::Integer(5),
+                Literal::None,
+                Literal::Float(5.0),
+                Literal::None,
+            ]
+        );
+        assert_eq!(tokens[2].token_type, TokenType::Dot);
+    }
+
+    #[test]
+    fn double_dot_after_integer_is_two_dots() {
+        let (tokens, _) = scan_all("5..method()");
+        assert_eq!(
+            types(&tokens),
+            [
+                TokenType::Number,
+                TokenType::Dot,
+                TokenType::Dot,
+                TokenType::Identifier,
+                TokenType::LeftParen,
+                TokenType::RightParen,
+                TokenType::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn hex_escapes() {
+        let (tokens, errors) = scan_all(r#""\x41\x7e" "\x4" "\x4g""#);
+        assert_eq!(tokens[0].literal, Literal::String("A~".to_string()));
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(
+            messages(&errors),
+            [
+                "[line: 1] Error : Invalid \\x escape; expected two hex digits.",
+                "[line: 1] Error : Invalid \\x escape; expected two hex digits.",
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_compare_as_a_whole() {
+        let (tokens, _) = scan_all("\n  \"hi\"");
+        assert_eq!(
+            tokens[0],
+            Token {
+                token_type: TokenType::String,
+                lexeme: Rc::from("\"hi\""),
+                literal: Literal::String("hi".to_string()),
+                line: 2,
+                column: 3,
+                symbol: None,
+            }
+        );
+    }
+
+    #[test]
+    fn keywords_are_matched_exactly() {
+        let all = [
+            ("and", TokenType::And),
+            ("class", TokenType::Class),
+            ("else", TokenType::Else),
+            ("false", TokenType::False),
+            ("for", TokenType::For),
+            ("fun", TokenType::Fun),
+            ("if", TokenType::If),
+            ("nil", TokenType::Nil),
+            ("or", TokenType::Or),
+            ("print", TokenType::Print),
+            ("return", TokenType::Return),
+            ("super", TokenType::Super),
+            ("this", TokenType::This),
+            ("true", TokenType::True),
+            ("var", TokenType::Var),
+            ("while", TokenType::While),
+        ];
+        for (text, token_type) in all {
+            assert_eq!(keyword(text), Some(token_type));
+        }
+
+        let (tokens, _) = scan_all("classy ifx If");
+        assert_eq!(
+            types(&tokens),
+            [
+                TokenType::Identifier,
+                TokenType::Identifier,
+                TokenType::Identifier,
+                TokenType::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn rescan_before_any_scan_scans_in_full() {
+        let mut scanner = Scanner::new("abc".to_string());
+        let tokens = scanner.rescan(0..0, "\n");
+        assert_eq!(types(tokens), [TokenType::Identifier, TokenType::Eof]);
+        assert_eq!(tokens[0].line, 2);
+
+        scanner.reset("x".to_string());
+        let tokens = scanner.rescan(1..1, "y");
+        assert_eq!(&*tokens[0].lexeme, "xy");
+    }
+
+    #[test]
+    #[should_panic(expected = "scan_reader doesn't keep")]
+    fn rescan_after_scan_reader_panics() {
+        let mut scanner = Scanner::new(String::new());
+        scanner
+            .scan_reader("var a = 1;\nprint a;\n".as_bytes())
+            .unwrap();
+        scanner.rescan(0..0, "x");
+    }
+
+    #[test]
+    #[should_panic(expected = "reversed edit range")]
+    fn rescan_rejects_reversed_range() {
+        let mut scanner = Scanner::new("abcdef".to_string());
+        scanner.scan_tokens();
+        scanner.rescan(Range { start: 4, end: 2 }, "X");
+    }
+
+    #[test]
+    fn symbolic_and_or_match_the_keywords() {
+        assert_eq!(
+            types(&scan_all("true && false").0),
+            types(&scan_all("true and false").0)
+        );
+        assert_eq!(
+            types(&scan_all("false || true").0),
+            types(&scan_all("false or true").0)
+        );
+    }
+
+    #[test]
+    fn scan_tokens_stops_at_max_errors() {
+        let (tokens, errors) = scan_all(&"@".repeat(1000));
+        assert_eq!(types(&tokens), [TokenType::Eof]);
+        assert_eq!(errors.len(), DEFAULT_MAX_ERRORS + 1);
+        assert_eq!(errors.last().unwrap().message, "Too many errors; aborting.");
+    }
+}
@@ -0,0 +1,217 @@
+use std::rc::Rc;
+
+use crate::intern::Symbol;
+
+/// Floats compare with `==`, so a NaN never equals anything. The scanner
+/// can't produce one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Integer(i32),
+    Float(f32),
+    String(String),
+    Boolean(bool),
+    Nil,
+    // The token has no literal value, as opposed to the nil literal.
+    None,
+}
+
+impl Literal {
+    /// Integer and float literals alike, widened to `f64`.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Literal::Integer(value) => Some(*value as f64),
+            Literal::Float(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Literal::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Literal::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub lexeme: Rc<str>,
+    pub literal: Literal, // Object?
+    pub line: usize,
+    /// 1-based, counted in characters.
+    pub column: usize,
+    /// Set for identifiers and keywords, whose lexemes are interned.
+    pub symbol: Option<Symbol>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TokenType {
+    // Single-character tokens.
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    Comma,
+    Dot,
+    Minus,
+    Plus,
+    Semicolon,
+    Slash,
+    Star,
+
+    // One or two character tokens.
+    Bang,
+    BangEqual,
+    Equal,
+    EqualEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+
+    // Literals.
+    Identifier,
+    String,
+    Number,
+
+    // Keywords.
+    And,
+    Class,
+    Else,
+    False,
+    Fun,
+    For,
+    If,
+    Nil,
+    Or,
+    Print,
+    Return,
+    Super,
+    This,
+    True,
+    Var,
+    While,
+
+    // Only emitted when newlines are significant.
+    Newline,
+
+    Eof,
+}
+
+impl TokenType {
+    pub fn is_keyword(&self) -> bool {
+        matches!(
+            self,
+            TokenType::And
+                | TokenType::Class
+                | TokenType::Else
+                | TokenType::False
+                | TokenType::Fun
+                | TokenType::For
+                | TokenType::If
+                | TokenType::Nil
+                | TokenType::Or
+                | TokenType::Print
+                | TokenType::Return
+                | TokenType::Super
+                | TokenType::This
+                | TokenType::True
+                | TokenType::Var
+                | TokenType::While
+        )
+    }
+
+    pub fn is_literal(&self) -> bool {
+        matches!(
+            self,
+            TokenType::Identifier | TokenType::String | TokenType::Number
+        )
+    }
+
+    /// Arithmetic, comparison and equality operators. `and`/`or` are
+    /// keywords and not included here.
+    pub fn is_binary_operator(&self) -> bool {
+        matches!(
+            self,
+            TokenType::Minus
+                | TokenType::Plus
+                | TokenType::Slash
+                | TokenType::Star
+                | TokenType::BangEqual
+                | TokenType::EqualEqual
+                | TokenType::Greater
+                | TokenType::GreaterEqual
+                | TokenType::Less
+                | TokenType::LessEqual
+        )
+    }
+
+    pub fn is_unary_operator(&self) -> bool {
+        matches!(self, TokenType::Bang | TokenType::Minus)
+    }
+}
+
+impl Token {
+    pub fn is_keyword(&self) -> bool {
+        self.token_type.is_keyword()
+    }
+
+    pub fn is_literal(&self) -> bool {
+        self.token_type.is_literal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_token_types() {
+        assert!(TokenType::While.is_keyword());
+        assert!(!TokenType::Identifier.is_keyword());
+        assert!(TokenType::Identifier.is_literal());
+        assert!(TokenType::Number.is_literal());
+        assert!(!TokenType::True.is_literal());
+        assert!(TokenType::LessEqual.is_binary_operator());
+        assert!(!TokenType::And.is_binary_operator());
+        assert!(TokenType::Minus.is_binary_operator());
+        assert!(TokenType::Minus.is_unary_operator());
+        assert!(TokenType::Bang.is_unary_operator());
+        assert!(!TokenType::Plus.is_unary_operator());
+    }
+
+    #[test]
+    fn token_types_hash_and_compare() {
+        use std::collections::HashSet;
+
+        let set: HashSet<TokenType> = [TokenType::Plus, TokenType::Minus, TokenType::Plus]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&TokenType::Minus));
+        assert_eq!(TokenType::Plus, TokenType::Plus);
+        assert_ne!(TokenType::Plus, TokenType::Minus);
+    }
+
+    #[test]
+    fn literal_accessors() {
+        assert_eq!(Literal::Integer(3).as_number(), Some(3.0));
+        assert_eq!(Literal::Float(0.5).as_number(), Some(0.5));
+        assert_eq!(Literal::String("3".to_string()).as_number(), None);
+
+        assert_eq!(Literal::String("hi".to_string()).as_string(), Some("hi"));
+        assert_eq!(Literal::Nil.as_string(), None);
+
+        assert_eq!(Literal::Boolean(false).as_bool(), Some(false));
+        assert_eq!(Literal::Nil.as_bool(), None);
+        assert_eq!(Literal::None.as_bool(), None);
+    }
+}